@@ -0,0 +1,94 @@
+//! Async bus sharing, modeled on rtic-sync's `Arbiter`.
+//!
+//! Unlike the blocking [`CommonBus::lock`], [`CommonBus::lock_async`] never panics on conflict:
+//! if the bus is already held, the caller's `Waker` is pushed onto an intrusive wait queue and
+//! the task yields `Poll::Pending` until the bus is released, at which point the next queued
+//! waiter is popped and woken. This requires the `eh1` feature, since `embedded-hal-async`'s
+//! `I2c` and `SpiDevice` traits share their `ErrorType` with `embedded-hal` 1.0.
+
+use core::{future::poll_fn, pin::pin, sync::atomic::Ordering, task::Poll};
+
+use rtic_common::{dropper::OnDrop, wait_queue::Link};
+
+use crate::{AtomicMutex, CommonBus};
+
+// Async waiting polls the atomic busy flag directly, so it is only available for the default
+// `AtomicMutex` locking strategy; `CriticalSectionMutex` and `NullMutex` have no busy state to
+// register a waiter against.
+impl<BUS> CommonBus<BUS, AtomicMutex> {
+    /// Asynchronously locks the bus, waiting for any other task holding it to release it, and
+    /// runs `f` with exclusive access.
+    ///
+    /// If the returned future is dropped before completion, the bus is released and the next
+    /// waiter is woken, so a cancelled caller can never leave the bus permanently locked.
+    pub async fn lock_async<R>(&self, f: impl AsyncFnOnce(&mut BUS) -> R) -> R {
+        // `poll_fn` below needs the current task's `Waker` to register with the wait queue, but
+        // an `async fn` body has no direct way to read it outside of a `Context`. Pull it out
+        // with a trivial `poll_fn` that resolves on its very first poll.
+        let waker = poll_fn(|cx| Poll::Ready(cx.waker().clone())).await;
+
+        // Pinned once for the whole call, so the node stays linked (and its address stays
+        // valid) across every poll of this same future. `poll_fn` invokes its closure fresh on
+        // each poll, so a `Link` created and pinned *inside* that closure would be dropped the
+        // instant the closure returns `Poll::Pending`, unlinking the waiter before `release()`
+        // could ever find it.
+        let link = pin!(Link::new(waker));
+        let mut pushed = false;
+
+        poll_fn(|_cx| {
+            if self
+                .mutex
+                .busy
+                .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+            {
+                return Poll::Ready(());
+            }
+
+            if !pushed {
+                // SAFETY: `link` is pinned for the remainder of this function, which outlives
+                // its time spent in the wait queue: either this future runs to completion past
+                // the point where the queue could still observe it, or it is dropped, which
+                // unlinks `link` before its storage goes away.
+                unsafe { self.waiters.push(link.as_ref()) };
+                pushed = true;
+            }
+
+            Poll::Pending
+        })
+        .await;
+
+        let _on_drop = OnDrop::new(|| self.release());
+
+        f(unsafe { &mut *self.bus.get() }).await
+    }
+
+    fn release(&self) {
+        self.mutex.busy.store(false, Ordering::SeqCst);
+        if let Some(waker) = self.waiters.pop() {
+            waker.wake();
+        }
+    }
+}
+
+impl<BUS: embedded_hal_async::i2c::I2c> embedded_hal_async::i2c::I2c for &CommonBus<BUS, AtomicMutex> {
+    async fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [embedded_hal_async::i2c::Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.lock_async(|bus| bus.transaction(address, operations))
+            .await
+    }
+}
+
+impl<BUS: embedded_hal_async::spi::SpiDevice> embedded_hal_async::spi::SpiDevice
+    for &CommonBus<BUS, AtomicMutex>
+{
+    async fn transaction(
+        &mut self,
+        operations: &mut [embedded_hal_async::spi::Operation<'_, u8>],
+    ) -> Result<(), Self::Error> {
+        self.lock_async(|bus| bus.transaction(operations)).await
+    }
+}
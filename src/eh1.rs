@@ -0,0 +1,131 @@
+//! Implementations of the `embedded-hal` 1.0 `I2c` and `SpiDevice` traits.
+//!
+//! These mirror the `embedded-hal` 0.2 impls in the crate root, but forward to the `embedded-hal` 1.0
+//! `transaction()` entry point so that a whole batch of `Operation`s is executed while the
+//! underlying bus is locked exactly once, guaranteeing no other device can preempt mid-sequence.
+//!
+//! `embedded-hal` 0.2 and 1.0 are separate crates that both happen to be named `embedded-hal`, so
+//! the 1.0 dependency is renamed in `Cargo.toml` (`embedded-hal-1 = { package = "embedded-hal",
+//! version = "1" }`) and imported here as `embedded_hal_1` to avoid colliding with the 0.2 import
+//! used by the rest of the crate.
+
+use crate::{CommonBus, MutexKind};
+use embedded_hal_1::{digital::OutputPin, i2c, spi, spi::SpiBus};
+
+impl<BUS: i2c::ErrorType, M: MutexKind> i2c::ErrorType for &CommonBus<BUS, M> {
+    type Error = BUS::Error;
+}
+
+impl<BUS: i2c::I2c, M: MutexKind> i2c::I2c for &CommonBus<BUS, M> {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [i2c::Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.lock(|bus| bus.transaction(address, operations))
+    }
+}
+
+impl<BUS: spi::ErrorType, M: MutexKind> spi::ErrorType for &CommonBus<BUS, M> {
+    type Error = BUS::Error;
+}
+
+impl<BUS: spi::SpiDevice, M: MutexKind> spi::SpiDevice for &CommonBus<BUS, M> {
+    fn transaction(&mut self, operations: &mut [spi::Operation<'_, u8>]) -> Result<(), Self::Error> {
+        self.lock(|bus| bus.transaction(operations))
+    }
+}
+
+/// A single SPI device sharing a [`CommonBus`], with its own chip-select pin and delay source.
+///
+/// CS is asserted and deasserted inside the same `lock()` call that performs the transfer, so
+/// the bus can never be handed to another device while this device's CS is still held low.
+pub struct SpiBusDevice<BUS: 'static, CS, DELAY, M: MutexKind + 'static = crate::AtomicMutex> {
+    bus: &'static CommonBus<BUS, M>,
+    cs: CS,
+    delay: DELAY,
+}
+
+impl<BUS: 'static, M: MutexKind + 'static> CommonBus<BUS, M> {
+    /// Creates a handle for a single device on the shared SPI bus, binding a chip-select pin
+    /// and a delay source (used to service `Operation::DelayNs`) to it. The bus itself is only
+    /// locked for the duration of each `transaction()`.
+    pub fn acquire_spi<CS: OutputPin, DELAY: embedded_hal_1::delay::DelayNs>(
+        &'static self,
+        cs: CS,
+        delay: DELAY,
+    ) -> SpiBusDevice<BUS, CS, DELAY, M> {
+        SpiBusDevice {
+            bus: self,
+            cs,
+            delay,
+        }
+    }
+}
+
+/// Combines the underlying bus error and chip-select pin error for [`SpiBusDevice`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SpiDeviceError<BusError, PinError> {
+    /// An error occurred while accessing the underlying bus.
+    Bus(BusError),
+    /// An error occurred while driving the chip-select pin.
+    Cs(PinError),
+}
+
+impl<BusError: spi::Error, PinError: core::fmt::Debug> spi::Error for SpiDeviceError<BusError, PinError> {
+    fn kind(&self) -> spi::ErrorKind {
+        match self {
+            SpiDeviceError::Bus(e) => e.kind(),
+            SpiDeviceError::Cs(_) => spi::ErrorKind::ChipSelectFault,
+        }
+    }
+}
+
+impl<BUS, CS, DELAY, M> spi::ErrorType for SpiBusDevice<BUS, CS, DELAY, M>
+where
+    BUS: spi::ErrorType + 'static,
+    CS: OutputPin,
+    M: MutexKind + 'static,
+{
+    type Error = SpiDeviceError<BUS::Error, CS::Error>;
+}
+
+impl<BUS, CS, DELAY, M> spi::SpiDevice for SpiBusDevice<BUS, CS, DELAY, M>
+where
+    BUS: spi::SpiBus + 'static,
+    CS: OutputPin,
+    DELAY: embedded_hal_1::delay::DelayNs,
+    M: MutexKind + 'static,
+{
+    fn transaction(&mut self, operations: &mut [spi::Operation<'_, u8>]) -> Result<(), Self::Error> {
+        let bus = self.bus;
+        let cs = &mut self.cs;
+        let delay = &mut self.delay;
+
+        bus.lock(|raw| {
+            cs.set_low().map_err(SpiDeviceError::Cs)?;
+
+            // `SpiBus` has no `transaction()` of its own (that's a `SpiDevice` method), so
+            // dispatch each operation to the matching `SpiBus` call ourselves, the same way
+            // `embedded-hal-bus`'s `ExclusiveDevice`/`AtomicDevice` do.
+            let result = operations
+                .iter_mut()
+                .try_for_each(|op| match op {
+                    spi::Operation::Read(buf) => raw.read(buf),
+                    spi::Operation::Write(buf) => raw.write(buf),
+                    spi::Operation::Transfer(read, write) => raw.transfer(read, write),
+                    spi::Operation::TransferInPlace(buf) => raw.transfer_in_place(buf),
+                    spi::Operation::DelayNs(ns) => {
+                        delay.delay_ns(*ns);
+                        Ok(())
+                    }
+                })
+                .and_then(|()| raw.flush())
+                .map_err(SpiDeviceError::Bus);
+
+            cs.set_high().map_err(SpiDeviceError::Cs)?;
+
+            result
+        })
+    }
+}
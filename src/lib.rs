@@ -46,36 +46,62 @@
 //! }
 //! ```
 
-use core::sync::atomic::{AtomicBool, Ordering};
 use embedded_hal::{
     blocking::{self, i2c},
     spi,
 };
 
+mod mutex;
+pub use mutex::{AtomicMutex, CriticalSectionMutex, MutexKind, NullMutex};
+
 /// A convenience type to use for declaring the underlying bus type.
-pub type SharedBus<T> = &'static CommonBus<T>;
+pub type SharedBus<T, M = AtomicMutex> = &'static CommonBus<T, M>;
+
+/// A [`CommonBus`] that serializes access with a global critical section instead of an atomic
+/// busy flag.
+pub type CriticalSectionBus<T> = CommonBus<T, CriticalSectionMutex>;
+
+/// A [`CommonBus`] that performs no locking at all, for single-context use where the caller has
+/// already proven exclusivity.
+pub type NullMutexBus<T> = CommonBus<T, NullMutex>;
 
-pub struct CommonBus<BUS> {
+/// Returned by [`CommonBus::try_transaction`] when another caller already holds the bus.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct BusBusy;
+
+pub struct CommonBus<BUS, M: MutexKind = AtomicMutex> {
     bus: core::cell::UnsafeCell<BUS>,
-    busy: AtomicBool,
+    mutex: M,
+    #[cfg(feature = "async")]
+    waiters: rtic_common::wait_queue::DoublyLinkedList<core::task::Waker>,
 }
 
-impl<BUS> CommonBus<BUS> {
+impl<BUS, M: MutexKind> CommonBus<BUS, M> {
     pub fn new(bus: BUS) -> Self {
         CommonBus {
             bus: core::cell::UnsafeCell::new(bus),
-            busy: AtomicBool::from(false),
+            mutex: M::new(),
+            #[cfg(feature = "async")]
+            waiters: rtic_common::wait_queue::DoublyLinkedList::new(),
         }
     }
 
-    fn lock<R, F: FnOnce(&mut BUS) -> R>(&self, f: F) -> R {
-        atomic::compare_exchange(&self.busy, false, true, Ordering::SeqCst, Ordering::SeqCst)
-            .expect("Bus conflict");
-        let result = f(unsafe { &mut *self.bus.get() });
-
-        self.busy.store(false, Ordering::SeqCst);
+    pub(crate) fn lock<R, F: FnOnce(&mut BUS) -> R>(&self, f: F) -> R {
+        self.mutex.lock(&self.bus, f)
+    }
 
-        result
+    /// Attempts to lock the bus and run `f` with exclusive access, returning `Err(BusBusy)`
+    /// instead of panicking if another caller already holds it.
+    ///
+    /// Use this when a conflict is possible but recoverable, e.g. a low-priority task polling a
+    /// sensor while a higher-priority ISR may also touch the bus. Callers who have proven
+    /// exclusivity through RTIC resource priorities can keep using the infallible `acquire()`
+    /// path instead.
+    ///
+    /// Only [`AtomicMutex`] tracks whether the bus is held; with [`CriticalSectionMutex`] and
+    /// [`NullMutex`] this always returns `Ok`.
+    pub fn try_transaction<R, F: FnOnce(&mut BUS) -> R>(&self, f: F) -> Result<R, BusBusy> {
+        self.mutex.try_lock(&self.bus, f)
     }
 
     pub fn acquire(&self) -> &Self {
@@ -83,9 +109,9 @@ impl<BUS> CommonBus<BUS> {
     }
 }
 
-unsafe impl<BUS> Sync for CommonBus<BUS> {}
+unsafe impl<BUS, M: MutexKind> Sync for CommonBus<BUS, M> {}
 
-impl<BUS: i2c::Read> i2c::Read for &CommonBus<BUS> {
+impl<BUS: i2c::Read, M: MutexKind> i2c::Read for &CommonBus<BUS, M> {
     type Error = BUS::Error;
 
     fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
@@ -93,7 +119,7 @@ impl<BUS: i2c::Read> i2c::Read for &CommonBus<BUS> {
     }
 }
 
-impl<BUS: i2c::Write> i2c::Write for &CommonBus<BUS> {
+impl<BUS: i2c::Write, M: MutexKind> i2c::Write for &CommonBus<BUS, M> {
     type Error = BUS::Error;
 
     fn write(&mut self, address: u8, buffer: &[u8]) -> Result<(), Self::Error> {
@@ -101,7 +127,7 @@ impl<BUS: i2c::Write> i2c::Write for &CommonBus<BUS> {
     }
 }
 
-impl<BUS: i2c::WriteRead> i2c::WriteRead for &CommonBus<BUS> {
+impl<BUS: i2c::WriteRead, M: MutexKind> i2c::WriteRead for &CommonBus<BUS, M> {
     type Error = BUS::Error;
 
     fn write_read(
@@ -117,7 +143,7 @@ impl<BUS: i2c::WriteRead> i2c::WriteRead for &CommonBus<BUS> {
 macro_rules! spi {
     ($($T:ty),*) => {
         $(
-        impl<BUS: blocking::spi::Write<$T>> blocking::spi::Write<$T> for &CommonBus<BUS> {
+        impl<BUS: blocking::spi::Write<$T>, M: MutexKind> blocking::spi::Write<$T> for &CommonBus<BUS, M> {
             type Error = BUS::Error;
 
             fn write(&mut self, words: &[$T]) -> Result<(), Self::Error> {
@@ -125,7 +151,7 @@ macro_rules! spi {
             }
         }
 
-        impl<BUS: blocking::spi::Transfer<$T>> blocking::spi::Transfer<$T> for &CommonBus<BUS> {
+        impl<BUS: blocking::spi::Transfer<$T>, M: MutexKind> blocking::spi::Transfer<$T> for &CommonBus<BUS, M> {
             type Error = BUS::Error;
 
             fn transfer<'w>(&mut self, words: &'w mut [$T]) -> Result<&'w [$T], Self::Error> {
@@ -133,7 +159,7 @@ macro_rules! spi {
             }
         }
 
-        impl<BUS: spi::FullDuplex<$T>> spi::FullDuplex<$T> for &CommonBus<BUS> {
+        impl<BUS: spi::FullDuplex<$T>, M: MutexKind> spi::FullDuplex<$T> for &CommonBus<BUS, M> {
             type Error = BUS::Error;
 
             fn read(&mut self) -> nb::Result<$T, Self::Error> {
@@ -150,51 +176,28 @@ macro_rules! spi {
 
 spi!(u8, u16, u32, u64);
 
-#[cfg(feature = "thumbv6")]
-mod atomic {
-    use core::sync::atomic::{AtomicBool, Ordering};
-
-    #[inline(always)]
-    pub fn compare_exchange(
-        atomic: &AtomicBool,
-        current: bool,
-        new: bool,
-        _success: Ordering,
-        _failure: Ordering,
-    ) -> Result<bool, bool> {
-        cortex_m::interrupt::free(|_cs| {
-            let prev = atomic.load(Ordering::Acquire);
-            if prev == current {
-                atomic.store(new, Ordering::Release);
-                Ok(prev)
-            } else {
-                Err(false)
-            }
-        })
-    }
-}
-
-#[cfg(not(feature = "thumbv6"))]
-mod atomic {
-    use core::sync::atomic::{AtomicBool, Ordering};
-
-    #[inline(always)]
-    pub fn compare_exchange(
-        atomic: &AtomicBool,
-        current: bool,
-        new: bool,
-        success: Ordering,
-        failure: Ordering,
-    ) -> Result<bool, bool> {
-        atomic.compare_exchange(current, new, success, failure)
-    }
-}
+/// Implementations of the `embedded-hal` 1.0 `I2c`/`SpiDevice` traits.
+///
+/// Enable the `eh1` feature to use `shared-bus-rtic` with drivers built against `embedded-hal`
+/// 1.0 instead of the 0.2 `blocking` traits implemented above.
+#[cfg(feature = "eh1")]
+mod eh1;
+#[cfg(feature = "eh1")]
+pub use eh1::{SpiBusDevice, SpiDeviceError};
+
+/// `async` bus sharing backed by an intrusive wait queue instead of a hard panic on conflict.
+///
+/// Requires the `async` feature (which implies `eh1`).
+#[cfg(feature = "async")]
+mod asynch;
 
 /// Provides a method of generating a shared bus.
 ///
 /// ## Args:
 /// * `bus` - The actual bus that should be shared
 /// * `T` - The full type of the bus that is being shared.
+/// * `M` - (optional) The [`MutexKind`](shared_bus_rtic::MutexKind) to use for locking the bus.
+///   Defaults to [`AtomicMutex`](shared_bus_rtic::AtomicMutex).
 ///
 /// ## Example:
 /// ```rust
@@ -206,11 +209,15 @@ mod atomic {
 #[macro_export]
 macro_rules! new {
     ($bus:ident, $T:ty) => {
+        shared_bus_rtic::new!($bus, $T, shared_bus_rtic::AtomicMutex)
+    };
+
+    ($bus:ident, $T:ty, $M:ty) => {
         unsafe {
-            static mut _MANAGER: core::mem::MaybeUninit<shared_bus_rtic::CommonBus<$T>> =
+            static mut _MANAGER: core::mem::MaybeUninit<shared_bus_rtic::CommonBus<$T, $M>> =
                 core::mem::MaybeUninit::uninit();
             _MANAGER = core::mem::MaybeUninit::new(shared_bus_rtic::CommonBus::new($bus));
             &*_MANAGER.as_ptr()
-        };
+        }
     };
 }
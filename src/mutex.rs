@@ -0,0 +1,120 @@
+//! Pluggable bus locking strategies for [`CommonBus`](crate::CommonBus).
+//!
+//! `CommonBus<BUS, M>` is generic over how exclusive access is enforced so that one crate can
+//! cover the full spectrum of sharing scenarios, following the approach taken by `shared-bus` and
+//! `embassy-embedded-hal`:
+//!
+//! * [`AtomicMutex`] (the default) claims an atomic busy flag and panics on conflict, exactly as
+//!   before.
+//! * [`CriticalSectionMutex`] disables interrupts for the duration of the access.
+//! * [`NullMutex`] performs no locking at all, for single-context use where the caller has
+//!   already proven exclusivity and wants zero overhead.
+
+use crate::BusBusy;
+use core::{cell::UnsafeCell, sync::atomic::Ordering};
+use portable_atomic::AtomicBool;
+
+/// A bus locking strategy usable with [`CommonBus`](crate::CommonBus).
+pub trait MutexKind {
+    /// Constructs a new instance of this mutex kind in its initial, unlocked state.
+    fn new() -> Self;
+
+    /// Runs `f` with exclusive access to `bus`, aborting if exclusivity cannot be guaranteed.
+    fn lock<BUS, R>(&self, bus: &UnsafeCell<BUS>, f: impl FnOnce(&mut BUS) -> R) -> R;
+
+    /// Attempts to run `f` with exclusive access to `bus`, returning `Err(BusBusy)` instead of
+    /// aborting if exclusivity cannot be guaranteed right now.
+    fn try_lock<BUS, R>(
+        &self,
+        bus: &UnsafeCell<BUS>,
+        f: impl FnOnce(&mut BUS) -> R,
+    ) -> Result<R, BusBusy>;
+}
+
+/// Guards the bus with an atomic busy flag, claimed with a single compare-exchange: `lock` panics
+/// and `try_lock` returns `Err(BusBusy)` if the flag is already set. Uses `portable-atomic` so the
+/// correct compare-exchange implementation (native or critical-section-based) is selected
+/// automatically per target.
+pub struct AtomicMutex {
+    pub(crate) busy: AtomicBool,
+}
+
+impl MutexKind for AtomicMutex {
+    fn new() -> Self {
+        AtomicMutex {
+            busy: AtomicBool::new(false),
+        }
+    }
+
+    fn lock<BUS, R>(&self, bus: &UnsafeCell<BUS>, f: impl FnOnce(&mut BUS) -> R) -> R {
+        self.busy
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .expect("Bus conflict");
+        let result = f(unsafe { &mut *bus.get() });
+
+        self.busy.store(false, Ordering::SeqCst);
+
+        result
+    }
+
+    fn try_lock<BUS, R>(
+        &self,
+        bus: &UnsafeCell<BUS>,
+        f: impl FnOnce(&mut BUS) -> R,
+    ) -> Result<R, BusBusy> {
+        self.busy
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .map_err(|_| BusBusy)?;
+        let result = f(unsafe { &mut *bus.get() });
+
+        self.busy.store(false, Ordering::SeqCst);
+
+        Ok(result)
+    }
+}
+
+/// Serializes bus access with a global critical section (interrupts disabled) instead of an
+/// atomic busy flag. Since no other context can run concurrently within the critical
+/// section, locking never fails.
+pub struct CriticalSectionMutex;
+
+impl MutexKind for CriticalSectionMutex {
+    fn new() -> Self {
+        CriticalSectionMutex
+    }
+
+    fn lock<BUS, R>(&self, bus: &UnsafeCell<BUS>, f: impl FnOnce(&mut BUS) -> R) -> R {
+        critical_section::with(|_| f(unsafe { &mut *bus.get() }))
+    }
+
+    fn try_lock<BUS, R>(
+        &self,
+        bus: &UnsafeCell<BUS>,
+        f: impl FnOnce(&mut BUS) -> R,
+    ) -> Result<R, BusBusy> {
+        Ok(self.lock(bus, f))
+    }
+}
+
+/// Performs no locking at all. Intended for single-context use where the caller has already
+/// proven exclusivity (e.g. every device sharing the bus is only ever reachable from one RTIC
+/// priority) and wants the bus access to compile down to a plain field access.
+pub struct NullMutex;
+
+impl MutexKind for NullMutex {
+    fn new() -> Self {
+        NullMutex
+    }
+
+    fn lock<BUS, R>(&self, bus: &UnsafeCell<BUS>, f: impl FnOnce(&mut BUS) -> R) -> R {
+        f(unsafe { &mut *bus.get() })
+    }
+
+    fn try_lock<BUS, R>(
+        &self,
+        bus: &UnsafeCell<BUS>,
+        f: impl FnOnce(&mut BUS) -> R,
+    ) -> Result<R, BusBusy> {
+        Ok(f(unsafe { &mut *bus.get() }))
+    }
+}